@@ -2,6 +2,8 @@
 
 pub mod widgets;
 pub mod paint;
+pub mod font;
 
 pub use paint::Painter;
 pub use widgets::{Color, Rect, UiState, PixelSink, panel, label_baseline, progress, button};
+pub use font::{draw_text, draw_glyph, measure_text, FONT_W, FONT_H};
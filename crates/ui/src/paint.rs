@@ -2,11 +2,13 @@
 //!
 //! Two build faces behind the `devhost` feature flag:
 //! - **devhost (default):** in-memory RGBA surface (Vec<u32>) so Codespaces/CI can run.
-//! - **OS mode (no_std):** zero-alloc stub that keeps the API stable until HAL framebuffer lands.
+//! - **OS mode (no_std):** a pitch-aware view over a physical framebuffer
+//!   (e.g. the one Limine hands the kernel at boot), or a zero-alloc stub
+//!   surface if no framebuffer has been wired up yet.
 //!
 //! The `Painter` implements `crate::widgets::PixelSink`, so you can call
 //! `panel/progress/button` today. In devhost, they actually draw into RAM;
-//! in OS mode they are no-ops until you wire the real framebuffer.
+//! in OS mode, `new_framebuffer` makes them draw into real video memory.
 
 #![allow(dead_code)]
 #![allow(unused_variables)]
@@ -21,6 +23,13 @@ pub struct Painter {
     #[cfg(feature = "devhost")]
     buf: Vec<u32>, // RGBA8888
 
+    /// Physical framebuffer base and byte pitch (OS mode only). Null base
+    /// means "no framebuffer wired up yet" (the `new_os_stub` surface).
+    #[cfg(not(feature = "devhost"))]
+    fb: *mut u8,
+    #[cfg(not(feature = "devhost"))]
+    pitch: usize,
+
     frame_count: u64,
 }
 
@@ -36,13 +45,27 @@ impl Painter {
         Self { w, h, buf, frame_count: 0 }
     }
 
-    /// Create an OS-mode painter with a placeholder surface.
-    /// Replace with a framebuffer-backed constructor later.
+    /// Create an OS-mode painter with a placeholder surface (no backing
+    /// framebuffer yet); drawing into it is a no-op.
     #[cfg(not(feature = "devhost"))]
     pub fn new_os_stub(w: i32, h: i32) -> Self {
         let w = w.max(1);
         let h = h.max(1);
-        Self { w, h, frame_count: 0 }
+        Self { w, h, fb: core::ptr::null_mut(), pitch: 0, frame_count: 0 }
+    }
+
+    /// Create an OS-mode painter over a real physical framebuffer (e.g. the
+    /// one handed over by the Limine boot protocol): `ptr` is the mapped
+    /// base address, `pitch` the byte stride per row, and `w`/`h` the
+    /// visible resolution. Assumes a 32bpp XRGB8888-ish layout.
+    /// # Safety
+    /// `ptr` must point at `h * pitch` bytes of writable, mapped video
+    /// memory for the lifetime of this `Painter`.
+    #[cfg(not(feature = "devhost"))]
+    pub unsafe fn new_framebuffer(ptr: *mut u8, pitch: usize, w: i32, h: i32) -> Self {
+        let w = w.max(1);
+        let h = h.max(1);
+        Self { w, h, fb: ptr, pitch, frame_count: 0 }
     }
 
     // ---------- Surface info ----------
@@ -62,8 +85,11 @@ impl Painter {
         }
         #[cfg(not(feature = "devhost"))]
         {
-            // no-op until HAL is wired
-            let _ = (r, g, b);
+            for y in 0..self.h {
+                for x in 0..self.w {
+                    self.put_px(x, y, r, g, b);
+                }
+            }
         }
     }
 
@@ -79,7 +105,12 @@ impl Painter {
         }
         #[cfg(not(feature = "devhost"))]
         {
-            let _ = (x, y, r, g, b); // no-op until HAL is wired
+            if self.fb.is_null() { return; } // stub surface; nothing backs it yet
+            let px: u32 = ((r as u32) << 16) | ((g as u32) << 8) | (b as u32); // XRGB8888
+            let row = unsafe { self.fb.add((y as usize) * self.pitch) };
+            // Safety: `x < self.w` and the framebuffer contract guarantees
+            // `pitch` bytes per row are valid for the painter's lifetime.
+            unsafe { (row as *mut u32).add(x as usize).write_volatile(px); }
         }
     }
 
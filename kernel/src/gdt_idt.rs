@@ -2,20 +2,28 @@
 //!
 //! Goal:
 //! - **devhost (default)**: no-op init so CI/Codespaces builds run immediately.
-//! - **OS mode (no_std)**: leave bootloader-provided tables in place for first boot,
-//!   but expose `sti/cli/hlt` helpers and a single `init()` call site to wire the
-//!   real GDT/IDT later.
+//! - **OS mode (no_std)**: builds a minimal flat GDT plus a TSS carrying one
+//!   IST (Interrupt Stack Table) entry, loads both, and exposes the IST
+//!   index the double-fault gate should use. A double fault's most common
+//!   trigger is a stack fault, so that handler must not run on the
+//!   (possibly corrupt) current stack — `interrupts::init()` points the
+//!   double-fault IDT entry at `DOUBLE_FAULT_IST_INDEX` to give it a known-
+//!   good stack instead.
 //!
-//! Later (OS mode): we will create a proper TSS, GDT, and IDT (x86_64), program
-//! the timer IRQ, and point each entry to an ISR. That step will add the
-//! `x86_64` crate behind `#[cfg(not(feature = "devhost"))]` only.
+//! Also exposes `sti/cli/hlt` helpers used by the idle/panic loops.
 
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+/// IST slot (1-7) the double-fault gate should use. `0` means "no IST",
+/// i.e. run on the current stack — never what you want for double-fault.
+#[cfg(not(feature = "devhost"))]
+pub const DOUBLE_FAULT_IST_INDEX: u8 = 1;
+
 /// Initialize CPU tables.
 /// - In devhost: does nothing.
-/// - In OS mode: currently keeps bootloader tables; safe first mile.
+/// - In OS mode: builds a flat GDT + TSS (with the double-fault IST stack),
+///   loads them, then reloads the segment registers and task register.
 pub fn init() {
     #[cfg(feature = "devhost")]
     {
@@ -24,9 +32,7 @@ pub fn init() {
 
     #[cfg(all(not(feature = "devhost"), any(target_arch = "x86_64", target_arch = "x86")))]
     unsafe {
-        // First mile: keep the bootloader's GDT/IDT.
-        // Later, replace with explicit GDT/IDT/TSS setup and `lidt/lgdt`.
-        // (See TODOs below.)
+        os_tables::init();
     }
 }
 
@@ -48,6 +54,22 @@ pub fn disable_interrupts() {
     }
 }
 
+/// Is the current core's interrupt flag (IF) set? Used to save/restore the
+/// interrupt-enabled state around short interrupt-safe critical sections
+/// (see `kernel::logger`'s deferred ring guard) instead of unconditionally
+/// re-enabling interrupts that may have already been off.
+#[inline]
+pub fn interrupts_enabled() -> bool {
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    unsafe {
+        let flags: u64;
+        core::arch::asm!("pushfq; pop {}", out(reg) flags, options(nomem, preserves_flags));
+        flags & (1 << 9) != 0
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+    true
+}
+
 /// Halt the CPU until the next interrupt (saves cycles in idle/panic paths).
 #[inline]
 pub fn halt() {
@@ -68,26 +90,158 @@ pub fn cli_hlt() -> ! {
     }
 }
 
-// -----------------------------
-// OS-mode TODO (for later)
-// -----------------------------
-//
-// When you flip out of `devhost` and into real no_std OS mode:
-// 1) Add a conditional dependency in `kernel/Cargo.toml`:
-//
-//    [target.'cfg(not(feature = "devhost"))'.dependencies]
-//    x86_64 = "0.15"
-//
-// 2) Replace `init()` with real setup:
-//
-//    - Create a Task State Segment (TSS) with an IST entry for double-fault.
-//    - Build a GDT with: null, kernel_code, kernel_data, TSS.
-//    - Load GDT via `lgdt`; reload CS/DS (far jump).
-//    - Build an IDT; set handlers for at least: breakpoint, double-fault,
-//      page-fault, timer (PIT/HPET), keyboard (if using PS/2).
-//    - Load IDT via `lidt`.
-//    - In your PIT/HPET ISR, call `crate::time::Clock::tick()`.
-//
-// Using `x86_64::structures::{gdt::*, idt::*, tss::*}` keeps this code clear
-// and avoids manual bit-fiddling of descriptor words.
+#[cfg(all(not(feature = "devhost"), target_arch = "x86_64"))]
+mod os_tables {
+    //! Flat 64-bit GDT (null/code/data) plus a TSS whose `ist1` slot points
+    //! at a dedicated stack, so `interrupts::init()` can put the
+    //! double-fault gate on known-good memory instead of the faulting
+    //! thread's own (possibly overflowed) stack.
+
+    const IST_STACK_SIZE: usize = 4096 * 4; // 16 KiB
+
+    #[repr(align(16))]
+    struct IstStack([u8; IST_STACK_SIZE]);
+
+    static mut DOUBLE_FAULT_STACK: IstStack = IstStack([0; IST_STACK_SIZE]);
+
+    /// x86_64 Task State Segment (I/O bitmap omitted: `iomap_base` points
+    /// past the struct, so the CPU never consults it).
+    #[repr(C, packed)]
+    struct Tss {
+        reserved0: u32,
+        rsp: [u64; 3],
+        reserved1: u64,
+        ist: [u64; 7],
+        reserved2: u64,
+        reserved3: u16,
+        iomap_base: u16,
+    }
+
+    impl Tss {
+        const fn zero() -> Self {
+            Self {
+                reserved0: 0,
+                rsp: [0; 3],
+                reserved1: 0,
+                ist: [0; 7],
+                reserved2: 0,
+                reserved3: 0,
+                iomap_base: core::mem::size_of::<Tss>() as u16,
+            }
+        }
+    }
+
+    static mut TSS: Tss = Tss::zero();
+
+    /// 16-byte TSS descriptor (the long-mode system descriptor needs twice
+    /// the width of a code/data descriptor to hold the full 64-bit base).
+    #[repr(C, packed)]
+    struct TssDescriptor {
+        limit_low: u16,
+        base_low: u16,
+        base_mid: u8,
+        access: u8,
+        limit_high_flags: u8,
+        base_high: u8,
+        base_upper: u32,
+        reserved: u32,
+    }
+
+    impl TssDescriptor {
+        const fn missing() -> Self {
+            Self { limit_low: 0, base_low: 0, base_mid: 0, access: 0, limit_high_flags: 0, base_high: 0, base_upper: 0, reserved: 0 }
+        }
+
+        fn for_tss(base: u64, limit: u32) -> Self {
+            Self {
+                limit_low: limit as u16,
+                base_low: base as u16,
+                base_mid: (base >> 16) as u8,
+                access: 0x89, // present, DPL0, type 0x9 (64-bit TSS, available)
+                limit_high_flags: ((limit >> 16) & 0x0F) as u8,
+                base_high: (base >> 24) as u8,
+                base_upper: (base >> 32) as u32,
+                reserved: 0,
+            }
+        }
+    }
+
+    const SEL_KERNEL_CODE: u16 = 0x08;
+    const SEL_KERNEL_DATA: u16 = 0x10;
+    const SEL_TSS: u16 = 0x18;
+
+    #[repr(C, packed)]
+    struct Gdt {
+        null: u64,
+        kernel_code: u64,
+        kernel_data: u64,
+        tss: TssDescriptor,
+    }
+
+    // Flat 64-bit code/data descriptors: base/limit are ignored by the CPU
+    // in long mode for non-system segments, so only the access/flag bits
+    // (present, ring 0, code-vs-data, long-mode `L`) matter.
+    const KERNEL_CODE_DESC: u64 = 0x00209A0000000000;
+    const KERNEL_DATA_DESC: u64 = 0x0000920000000000;
+
+    static mut GDT: Gdt = Gdt {
+        null: 0,
+        kernel_code: KERNEL_CODE_DESC,
+        kernel_data: KERNEL_DATA_DESC,
+        tss: TssDescriptor::missing(),
+    };
+
+    #[repr(C, packed)]
+    struct DescriptorPointer {
+        limit: u16,
+        base: u64,
+    }
+
+    pub unsafe fn init() {
+        let stack_top = core::ptr::addr_of!(DOUBLE_FAULT_STACK.0) as u64 + IST_STACK_SIZE as u64;
+        TSS.ist[(super::DOUBLE_FAULT_IST_INDEX - 1) as usize] = stack_top;
+
+        let tss_base = core::ptr::addr_of!(TSS) as u64;
+        let tss_limit = (core::mem::size_of::<Tss>() - 1) as u32;
+        GDT.tss = TssDescriptor::for_tss(tss_base, tss_limit);
+
+        let gdt_ptr = DescriptorPointer {
+            limit: (core::mem::size_of::<Gdt>() - 1) as u16,
+            base: core::ptr::addr_of!(GDT) as u64,
+        };
+        core::arch::asm!("lgdt [{}]", in(reg) &gdt_ptr, options(readonly, nostack, preserves_flags));
+
+        reload_code_segment(SEL_KERNEL_CODE);
+        reload_data_segments(SEL_KERNEL_DATA);
+
+        core::arch::asm!("ltr {0:x}", in(reg) SEL_TSS, options(nostack, preserves_flags));
+    }
+
+    /// CS can't be loaded with a plain `mov`; push the target selector and
+    /// address and `retfq` into it instead.
+    #[inline(never)]
+    unsafe fn reload_code_segment(selector: u16) {
+        core::arch::asm!(
+            "push {sel}",
+            "lea {tmp}, [2f + rip]",
+            "push {tmp}",
+            "retfq",
+            "2:",
+            sel = in(reg) selector as u64,
+            tmp = lateout(reg) _,
+        );
+    }
+
+    unsafe fn reload_data_segments(selector: u16) {
+        core::arch::asm!(
+            "mov ds, ax",
+            "mov es, ax",
+            "mov fs, ax",
+            "mov gs, ax",
+            "mov ss, ax",
+            in("ax") selector,
+            options(nostack, preserves_flags),
+        );
+    }
+}
 
@@ -0,0 +1,251 @@
+//! PS/2 keyboard input for Asteria OS (dual mode).
+//!
+//! - **devhost (default)**: decodes stdin lines into `KeyEvent`s so the UI
+//!   loop is testable in CI without real hardware.
+//! - **OS mode (no_std)**: an IRQ1 handler reads scancodes from port 0x60,
+//!   decodes Scancode Set 1 (including the 0xE0 extended prefix and the
+//!   high-bit release flag), tracks Shift/Ctrl/Alt, and pushes `KeyEvent`s
+//!   into a lock-free ring buffer for the main loop to drain.
+//!
+//! Public API (stable):
+//!   input::init()                      // OS mode: registers the IRQ1 handler
+//!   input::poll() -> Option<KeyEvent>
+//!   input::apply_to_ui(ev, &mut ui)     // folds cursor/enter/space into UiState
+
+#![allow(dead_code)]
+#![allow(unused_variables)]
+
+use ui::UiState;
+
+/// Shift/Ctrl/Alt state at the time a key event was produced.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+/// A decoded key press or release.
+///
+/// `code` is either the printable ASCII byte the key produces, or one of
+/// the `KEY_*` constants below for keys with no ASCII representation.
+#[derive(Copy, Clone, Debug)]
+pub struct KeyEvent {
+    pub code: u8,
+    pub pressed: bool,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    const EMPTY: Self = Self { code: 0, pressed: false, modifiers: Modifiers { shift: false, ctrl: false, alt: false } };
+}
+
+// Non-ASCII key codes, chosen clear of the printable ASCII range.
+pub const KEY_ENTER: u8 = 0x80;
+pub const KEY_SPACE: u8 = b' '; // space is already printable ASCII
+pub const KEY_BACKSPACE: u8 = 0x81;
+pub const KEY_TAB: u8 = 0x82;
+pub const KEY_ESC: u8 = 0x83;
+pub const KEY_UP: u8 = 0x84;
+pub const KEY_DOWN: u8 = 0x85;
+pub const KEY_LEFT: u8 = 0x86;
+pub const KEY_RIGHT: u8 = 0x87;
+
+/// Pixels the cursor moves per arrow-key press when folded into `UiState`.
+const CURSOR_STEP: i32 = 8;
+
+/// Fold a key event into a `UiState` so keyboard input can drive `button`:
+/// arrow keys move the virtual cursor, Enter/Space act as the primary button.
+pub fn apply_to_ui(ev: KeyEvent, ui: &mut UiState) {
+    match ev.code {
+        KEY_UP => if ev.pressed { ui.mouse_y -= CURSOR_STEP; },
+        KEY_DOWN => if ev.pressed { ui.mouse_y += CURSOR_STEP; },
+        KEY_LEFT => if ev.pressed { ui.mouse_x -= CURSOR_STEP; },
+        KEY_RIGHT => if ev.pressed { ui.mouse_x += CURSOR_STEP; },
+        KEY_ENTER | KEY_SPACE => {
+            ui.just_pressed = ev.pressed && !ui.pressed;
+            ui.just_released = !ev.pressed && ui.pressed;
+            ui.pressed = ev.pressed;
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "devhost")]
+pub fn init() {
+    // Nothing to register; devhost reads stdin lazily from `poll()`.
+}
+
+#[cfg(not(feature = "devhost"))]
+pub use os_input::{init, poll};
+
+#[cfg(feature = "devhost")]
+pub use devhost_input::poll;
+
+#[cfg(not(feature = "devhost"))]
+mod os_input {
+    use super::{KeyEvent, Modifiers, KEY_BACKSPACE, KEY_DOWN, KEY_ENTER, KEY_ESC, KEY_LEFT, KEY_RIGHT, KEY_SPACE, KEY_TAB, KEY_UP};
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering::{Acquire, Relaxed, Release}};
+
+    const DATA_PORT: u16 = 0x60;
+    const IRQ1_VECTOR: u8 = 0x21; // PIC remaps IRQ1 to 0x21 (see `interrupts`).
+    const RING_CAP: usize = 64;
+
+    // SAFETY (single-producer/single-consumer): only `on_irq1` ever writes a
+    // slot, only `poll()` ever reads one, and the Release/Acquire pair on
+    // `TAIL`/`HEAD` below ensures a slot is fully written before the
+    // producer's index advance makes it visible, and fully read before the
+    // consumer's index advance lets the producer reuse it.
+    static mut RING: [KeyEvent; RING_CAP] = [KeyEvent::EMPTY; RING_CAP];
+    static HEAD: AtomicUsize = AtomicUsize::new(0); // consumer index
+    static TAIL: AtomicUsize = AtomicUsize::new(0); // producer index
+
+    static SHIFT: AtomicBool = AtomicBool::new(false);
+    static CTRL: AtomicBool = AtomicBool::new(false);
+    static ALT: AtomicBool = AtomicBool::new(false);
+    static EXTENDED: AtomicBool = AtomicBool::new(false);
+
+    pub fn init() {
+        crate::kernel::interrupts::register(IRQ1_VECTOR, on_irq1);
+    }
+
+    fn push(ev: KeyEvent) {
+        let tail = TAIL.load(Relaxed);
+        let next = (tail + 1) % RING_CAP;
+        if next == HEAD.load(Acquire) {
+            return; // full; drop the event rather than overwrite unread input
+        }
+        unsafe { RING[tail] = ev; }
+        TAIL.store(next, Release);
+    }
+
+    /// Drain the next decoded key event, if any.
+    pub fn poll() -> Option<KeyEvent> {
+        let head = HEAD.load(Relaxed);
+        if head == TAIL.load(Acquire) {
+            return None; // empty
+        }
+        let ev = unsafe { RING[head] };
+        HEAD.store((head + 1) % RING_CAP, Release);
+        Some(ev)
+    }
+
+    fn on_irq1() {
+        let byte = unsafe { inb(DATA_PORT) };
+
+        if byte == 0xE0 {
+            EXTENDED.store(true, Relaxed);
+            return;
+        }
+        let extended = EXTENDED.swap(false, Relaxed);
+
+        let released = (byte & 0x80) != 0;
+        let scancode = byte & 0x7F;
+        let pressed = !released;
+
+        match (extended, scancode) {
+            (false, 0x2A) | (false, 0x36) => { SHIFT.store(pressed, Relaxed); return; }
+            (false, 0x1D) | (true, 0x1D) => { CTRL.store(pressed, Relaxed); return; }
+            (false, 0x38) | (true, 0x38) => { ALT.store(pressed, Relaxed); return; }
+            _ => {}
+        }
+
+        let code = decode(extended, scancode);
+        if code == 0 {
+            return;
+        }
+
+        push(KeyEvent {
+            code,
+            pressed,
+            modifiers: Modifiers { shift: SHIFT.load(Relaxed), ctrl: CTRL.load(Relaxed), alt: ALT.load(Relaxed) },
+        });
+    }
+
+    /// Map a Scancode Set 1 make code to a `KeyEvent` code. Returns 0 for
+    /// keys this driver doesn't surface.
+    fn decode(extended: bool, scancode: u8) -> u8 {
+        if extended {
+            return match scancode {
+                0x48 => KEY_UP,
+                0x50 => KEY_DOWN,
+                0x4B => KEY_LEFT,
+                0x4D => KEY_RIGHT,
+                _ => 0,
+            };
+        }
+        match scancode {
+            0x1C => KEY_ENTER,
+            0x39 => KEY_SPACE,
+            0x0E => KEY_BACKSPACE,
+            0x0F => KEY_TAB,
+            0x01 => KEY_ESC,
+            0x02 => b'1', 0x03 => b'2', 0x04 => b'3', 0x05 => b'4', 0x06 => b'5',
+            0x07 => b'6', 0x08 => b'7', 0x09 => b'8', 0x0A => b'9', 0x0B => b'0',
+            0x10 => b'Q', 0x11 => b'W', 0x12 => b'E', 0x13 => b'R', 0x14 => b'T',
+            0x15 => b'Y', 0x16 => b'U', 0x17 => b'I', 0x18 => b'O', 0x19 => b'P',
+            0x1E => b'A', 0x1F => b'S', 0x20 => b'D', 0x21 => b'F', 0x22 => b'G',
+            0x23 => b'H', 0x24 => b'J', 0x25 => b'K', 0x26 => b'L',
+            0x2C => b'Z', 0x2D => b'X', 0x2E => b'C', 0x2F => b'V', 0x30 => b'B',
+            0x31 => b'N', 0x32 => b'M',
+            _ => 0,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn inb(port: u16) -> u8 {
+        let mut v: u8 = 0;
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        core::arch::asm!("in al, dx", in("dx") port, out("al") v, options(nomem, nostack, preserves_flags));
+        v
+    }
+}
+
+#[cfg(feature = "devhost")]
+mod devhost_input {
+    use super::{KeyEvent, Modifiers, KEY_DOWN, KEY_ENTER, KEY_LEFT, KEY_RIGHT, KEY_SPACE, KEY_UP};
+    use std::io::BufRead;
+    use std::sync::Mutex;
+
+    static QUEUE: Mutex<Vec<KeyEvent>> = Mutex::new(Vec::new());
+
+    /// Read the next stdin-driven key event, lazily pulling and decoding one
+    /// more line from stdin when the queue runs dry. Each line is either a
+    /// named key ("up"/"down"/"left"/"right"/"enter"/"space", case-insensitive) or
+    /// literal text, decoded one `KeyEvent` per character.
+    pub fn poll() -> Option<KeyEvent> {
+        let mut queue = QUEUE.lock().unwrap();
+        if queue.is_empty() {
+            let mut line = String::new();
+            let n = std::io::stdin().lock().read_line(&mut line).unwrap_or(0);
+            if n == 0 {
+                return None; // EOF
+            }
+            decode_line(line.trim_end_matches(['\n', '\r']), &mut queue);
+        }
+        if queue.is_empty() { None } else { Some(queue.remove(0)) }
+    }
+
+    fn decode_line(line: &str, out: &mut Vec<KeyEvent>) {
+        let named = match line.to_ascii_lowercase().as_str() {
+            "up" => Some(KEY_UP),
+            "down" => Some(KEY_DOWN),
+            "left" => Some(KEY_LEFT),
+            "right" => Some(KEY_RIGHT),
+            "enter" => Some(KEY_ENTER),
+            "space" => Some(KEY_SPACE),
+            _ => None,
+        };
+        let modifiers = Modifiers::default();
+        if let Some(code) = named {
+            out.push(KeyEvent { code, pressed: true, modifiers });
+            out.push(KeyEvent { code, pressed: false, modifiers });
+            return;
+        }
+        for ch in line.chars().filter(|c| c.is_ascii()) {
+            let code = ch.to_ascii_uppercase() as u8;
+            out.push(KeyEvent { code, pressed: true, modifiers });
+            out.push(KeyEvent { code, pressed: false, modifiers });
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! x86 interrupt subsystem for Asteria OS (dual mode).
+//!
+//! - **devhost (default)**: no-op stubs with the same signatures so kernel
+//!   code that registers/enables interrupts compiles and runs unchanged.
+//! - **OS mode (no_std)**: builds a 256-entry IDT, loads it with `lidt`,
+//!   installs CPU exception handlers that report through `log_error!` and
+//!   the panic path, and remaps the legacy 8259 PIC so hardware IRQs land
+//!   on vectors 0x20+ instead of colliding with CPU exceptions.
+//!
+//! Public API (stable):
+//!   interrupts::init()
+//!   interrupts::register(vector, handler)
+//!   interrupts::enable() / interrupts::disable()
+//!
+//! Drivers (keyboard, timer, ...) call `register()` with their PIC-remapped
+//! vector (0x20 + IRQ line) and get dispatched from the common IRQ stub
+//! below; CPU exceptions are wired directly to named handlers.
+
+#![allow(dead_code)]
+#![allow(unused_variables)]
+
+#[cfg(feature = "devhost")]
+pub fn init() {
+    // Nothing to do on the host build.
+}
+
+#[cfg(feature = "devhost")]
+pub fn register(_vector: u8, _handler: fn()) {
+    // No interrupt controller on the host build.
+}
+
+#[cfg(feature = "devhost")]
+pub fn enable() {}
+
+#[cfg(feature = "devhost")]
+pub fn disable() {}
+
+#[cfg(not(feature = "devhost"))]
+pub use os_interrupts::{disable, enable, init, register};
+
+#[cfg(not(feature = "devhost"))]
+mod os_interrupts {
+    use crate::log_error;
+
+    const PIC1_CMD: u16 = 0x20;
+    const PIC1_DATA: u16 = 0x21;
+    const PIC2_CMD: u16 = 0xA0;
+    const PIC2_DATA: u16 = 0xA1;
+    const PIC_IRQ_BASE: u8 = 0x20; // IRQ0 -> vector 0x20 .. IRQ15 -> vector 0x2F
+
+    const VEC_DIVIDE_BY_ZERO: u8 = 0x00;
+    const VEC_INVALID_OPCODE: u8 = 0x06;
+    const VEC_DOUBLE_FAULT: u8 = 0x08;
+    const VEC_GENERAL_PROTECTION: u8 = 0x0D;
+    const VEC_PAGE_FAULT: u8 = 0x0E;
+
+    // ----- Registrable handler table (for IRQ vectors) ----------------------
+
+    static mut HANDLERS: [Option<fn()>; 256] = [None; 256];
+
+    /// Register a handler for a vector (typically a PIC-remapped IRQ, 0x20-0x2F).
+    pub fn register(vector: u8, handler: fn()) {
+        unsafe {
+            HANDLERS[vector as usize] = Some(handler);
+        }
+    }
+
+    /// Globally enable maskable interrupts.
+    #[inline]
+    pub fn enable() {
+        crate::kernel::gdt_idt::enable_interrupts();
+    }
+
+    /// Globally disable maskable interrupts.
+    #[inline]
+    pub fn disable() {
+        crate::kernel::gdt_idt::disable_interrupts();
+    }
+
+    // ----- IDT -----------------------------------------------------------------
+
+    #[repr(C, packed)]
+    #[derive(Copy, Clone)]
+    struct IdtEntry {
+        offset_low: u16,
+        selector: u16,
+        ist: u8,
+        type_attr: u8,
+        offset_mid: u16,
+        offset_high: u32,
+        reserved: u32,
+    }
+
+    impl IdtEntry {
+        const fn missing() -> Self {
+            Self { offset_low: 0, selector: 0, ist: 0, type_attr: 0, offset_mid: 0, offset_high: 0, reserved: 0 }
+        }
+
+        /// Build a present, ring-0 interrupt-gate entry pointing at `handler`.
+        /// `ist` selects a Task State Segment IST slot (1-7) to switch to on
+        /// entry, or `0` to stay on whatever stack was already in use.
+        fn new(handler: u64, selector: u16, ist: u8) -> Self {
+            Self {
+                offset_low: handler as u16,
+                selector,
+                ist,
+                type_attr: 0x8E, // present, DPL0, 32/64-bit interrupt gate
+                offset_mid: (handler >> 16) as u16,
+                offset_high: (handler >> 32) as u32,
+                reserved: 0,
+            }
+        }
+    }
+
+    #[repr(C, packed)]
+    struct IdtPointer {
+        limit: u16,
+        base: u64,
+    }
+
+    static mut IDT: [IdtEntry; 256] = [IdtEntry::missing(); 256];
+
+    /// Minimal view of the frame the CPU pushes before an interrupt handler runs.
+    #[repr(C)]
+    pub struct InterruptStackFrame {
+        pub instruction_pointer: u64,
+        pub code_segment: u64,
+        pub cpu_flags: u64,
+        pub stack_pointer: u64,
+        pub stack_segment: u64,
+    }
+
+    /// Initialize the IDT, remap the PIC, and load both.
+    pub fn init() {
+        unsafe {
+            set_gate(VEC_DIVIDE_BY_ZERO, divide_by_zero_handler as u64, 0);
+            set_gate(VEC_INVALID_OPCODE, invalid_opcode_handler as u64, 0);
+            // Double fault's most common trigger is a stack fault, so it
+            // must not run on the (possibly already-overflowed) current
+            // stack; give it its own known-good IST stack instead.
+            set_gate(VEC_DOUBLE_FAULT, double_fault_handler as u64, crate::kernel::gdt_idt::DOUBLE_FAULT_IST_INDEX);
+            set_gate(VEC_GENERAL_PROTECTION, general_protection_handler as u64, 0);
+            set_gate(VEC_PAGE_FAULT, page_fault_handler as u64, 0);
+
+            install_irq_stubs();
+            remap_pic();
+            load_idt();
+        }
+    }
+
+    unsafe fn set_gate(vector: u8, handler: u64, ist: u8) {
+        // Kernel code segment selector of the flat GDT that `gdt_idt::init`
+        // loads into CS (the boot path runs it before `interrupts::init`).
+        let cs: u16 = 0x08;
+        IDT[vector as usize] = IdtEntry::new(handler, cs, ist);
+    }
+
+    unsafe fn load_idt() {
+        let ptr = IdtPointer {
+            limit: (core::mem::size_of::<[IdtEntry; 256]>() - 1) as u16,
+            base: core::ptr::addr_of!(IDT) as u64,
+        };
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        core::arch::asm!("lidt [{}]", in(reg) &ptr, options(readonly, nostack, preserves_flags));
+    }
+
+    // ----- CPU exception handlers -----------------------------------------------
+
+    extern "x86-interrupt" fn divide_by_zero_handler(_frame: InterruptStackFrame) {
+        log_error!("divide-by-zero exception");
+        panic!("CPU exception: divide-by-zero");
+    }
+
+    extern "x86-interrupt" fn invalid_opcode_handler(_frame: InterruptStackFrame) {
+        log_error!("invalid opcode exception");
+        panic!("CPU exception: invalid opcode");
+    }
+
+    extern "x86-interrupt" fn double_fault_handler(_frame: InterruptStackFrame, error_code: u64) -> ! {
+        log_error!("double fault, error_code={}", error_code);
+        panic!("CPU exception: double fault, error_code={}", error_code);
+    }
+
+    extern "x86-interrupt" fn general_protection_handler(_frame: InterruptStackFrame, error_code: u64) {
+        log_error!("general protection fault, error_code={}", error_code);
+        panic!("CPU exception: general protection fault, error_code={}", error_code);
+    }
+
+    extern "x86-interrupt" fn page_fault_handler(_frame: InterruptStackFrame, error_code: u64) {
+        let fault_addr: u64;
+        unsafe {
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            core::arch::asm!("mov {}, cr2", out(reg) fault_addr, options(nomem, nostack, preserves_flags));
+            #[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+            { fault_addr = 0; }
+        }
+        log_error!("page fault at {:#x}, error_code={}", fault_addr, error_code);
+        panic!("CPU exception: page fault at {:#x}", fault_addr);
+    }
+
+    // ----- Hardware IRQ dispatch -------------------------------------------------
+
+    /// Shared tail for every IRQ stub: run the registered handler (if any), then EOI.
+    fn common_irq_handler(irq_line: u8) {
+        let vector = PIC_IRQ_BASE + irq_line;
+        let handler = unsafe { HANDLERS[vector as usize] };
+        if let Some(f) = handler {
+            f();
+        }
+        send_eoi(irq_line);
+    }
+
+    fn send_eoi(irq_line: u8) {
+        unsafe {
+            if irq_line >= 8 {
+                outb(PIC2_CMD, 0x20);
+            }
+            outb(PIC1_CMD, 0x20);
+        }
+    }
+
+    macro_rules! irq_stub {
+        ($name:ident, $line:expr) => {
+            extern "x86-interrupt" fn $name(_frame: InterruptStackFrame) {
+                common_irq_handler($line);
+            }
+        };
+    }
+
+    irq_stub!(irq0, 0);
+    irq_stub!(irq1, 1);
+    irq_stub!(irq2, 2);
+    irq_stub!(irq3, 3);
+    irq_stub!(irq4, 4);
+    irq_stub!(irq5, 5);
+    irq_stub!(irq6, 6);
+    irq_stub!(irq7, 7);
+    irq_stub!(irq8, 8);
+    irq_stub!(irq9, 9);
+    irq_stub!(irq10, 10);
+    irq_stub!(irq11, 11);
+    irq_stub!(irq12, 12);
+    irq_stub!(irq13, 13);
+    irq_stub!(irq14, 14);
+    irq_stub!(irq15, 15);
+
+    unsafe fn install_irq_stubs() {
+        set_gate(PIC_IRQ_BASE, irq0 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 1, irq1 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 2, irq2 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 3, irq3 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 4, irq4 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 5, irq5 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 6, irq6 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 7, irq7 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 8, irq8 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 9, irq9 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 10, irq10 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 11, irq11 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 12, irq12 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 13, irq13 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 14, irq14 as u64, 0);
+        set_gate(PIC_IRQ_BASE + 15, irq15 as u64, 0);
+    }
+
+    /// Remap the legacy 8259 PIC pair so IRQ0-15 land on vectors 0x20-0x2F
+    /// instead of the CPU exception range they default to.
+    unsafe fn remap_pic() {
+        let m_mask = inb(PIC1_DATA);
+        let s_mask = inb(PIC2_DATA);
+
+        outb(PIC1_CMD, 0x11); // ICW1: init, expect ICW4
+        outb(PIC2_CMD, 0x11);
+        outb(PIC1_DATA, PIC_IRQ_BASE); // ICW2: master offset 0x20
+        outb(PIC2_DATA, PIC_IRQ_BASE + 8); // ICW2: slave offset 0x28
+        outb(PIC1_DATA, 0x04); // ICW3: slave attached on master's IRQ2
+        outb(PIC2_DATA, 0x02); // ICW3: slave's cascade identity
+        outb(PIC1_DATA, 0x01); // ICW4: 8086 mode
+        outb(PIC2_DATA, 0x01);
+
+        outb(PIC1_DATA, m_mask); // restore saved masks
+        outb(PIC2_DATA, s_mask);
+    }
+
+    #[inline(always)]
+    unsafe fn outb(port: u16, val: u8) {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        core::arch::asm!("out dx, al", in("dx") port, in("al") val, options(nomem, nostack, preserves_flags));
+    }
+
+    #[inline(always)]
+    unsafe fn inb(port: u16) -> u8 {
+        let mut v: u8 = 0;
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        core::arch::asm!("in al, dx", in("dx") port, out("al") v, options(nomem, nostack, preserves_flags));
+        v
+    }
+}
@@ -2,10 +2,23 @@
 //!
 //! - **devhost (default)**: pretty stderr with level + file:line + timestamp.
 //! - **OS mode (no_std)**: writes to serial COM1 (0x3F8) without allocation.
+//!   Can run in two modes:
+//!   * synchronous (default): `_log` blocks on the COM1 THR-empty bit for
+//!     every byte, same as before.
+//!   * deferred (`set_deferred(true)`): `_log` formats the record into a
+//!     fixed-size ring buffer and returns immediately — safe to call from
+//!     interrupt handlers, where blocking on 115200-baud serial would stall
+//!     the whole system. `flush()`, called from the main loop, drains the
+//!     ring to serial with interrupts enabled; the ring's lock is taken in
+//!     an interrupt-safe critical section so a same-core handler's `push()`
+//!     can never spin on a lock the main loop was preempted while holding.
 //!
 //! Public API:
 //!   logger::init();                 // set up sinks (devhost hook / serial init)
 //!   logger::set_level(Level::Info); // runtime min level
+//!   logger::set_deferred(bool);     // OS mode: toggle deferred logging
+//!   logger::flush();                // OS mode: drain the deferred ring to serial
+//!   logger::dropped_count();        // OS mode: records dropped because the ring was full
 //!   macros: log_error!, log_warn!, log_info!, log_debug!, log_trace!
 //!
 //! No dependencies; safe to use very early.
@@ -42,7 +55,7 @@ pub fn init() {
     devhost_init();
 
     #[cfg(not(feature = "devhost"))]
-    unsafe { serial_init(); }
+    unsafe { crate::kernel::serial::init(); }
 }
 
 #[cfg(feature = "devhost")]
@@ -77,27 +90,58 @@ pub fn _log(level: Level, file: &str, line: u32, args: core::fmt::Arguments) {
 
     #[cfg(not(feature = "devhost"))]
     {
-        // Prefix: [L] file:line
-        serial_write_b(b'[');
-        serial_write_str(match level {
-            Level::Error => "E",
-            Level::Warn  => "W",
-            Level::Info  => "I",
-            Level::Debug => "D",
-            Level::Trace => "T",
-        });
-        serial_write_b(b']'); serial_write_b(b' ');
-        serial_write_str(file); serial_write_b(b':');
-        decimal_u32(line);
-        serial_write_b(b' ');
-
-        // Body
-        let mut w = SerialWriter;
-        let _ = core::fmt::Write::write_fmt(&mut w, args);
-        serial_write_b(b'\r'); serial_write_b(b'\n');
+        if os_deferred::is_deferred() {
+            os_deferred::log_deferred(level, file, line, args);
+        } else {
+            log_sync(level, file, line, args);
+        }
     }
 }
 
+#[cfg(not(feature = "devhost"))]
+fn log_sync(level: Level, file: &str, line: u32, args: core::fmt::Arguments) {
+    use crate::kernel::serial;
+
+    // Prefix: [L] file:line
+    serial::write_byte(b'[');
+    serial::write_str(match level {
+        Level::Error => "E",
+        Level::Warn  => "W",
+        Level::Info  => "I",
+        Level::Debug => "D",
+        Level::Trace => "T",
+    });
+    serial::write_byte(b']'); serial::write_byte(b' ');
+    serial::write_str(file); serial::write_byte(b':');
+    serial::decimal_u32(line);
+    serial::write_byte(b' ');
+
+    // Body
+    let mut w = serial::Writer;
+    let _ = core::fmt::Write::write_fmt(&mut w, args);
+    serial::write_byte(b'\r'); serial::write_byte(b'\n');
+}
+
+/// OS mode: toggle deferred logging. Off by default so early boot (before
+/// the main loop exists to call `flush()`) still logs synchronously.
+#[cfg(not(feature = "devhost"))]
+pub fn set_deferred(on: bool) {
+    os_deferred::DEFERRED.store(on, Relaxed);
+}
+
+/// OS mode: drain the deferred ring buffer to serial. Call from the main
+/// loop with interrupts enabled.
+#[cfg(not(feature = "devhost"))]
+pub fn flush() {
+    os_deferred::flush();
+}
+
+/// OS mode: count of records dropped because the deferred ring was full.
+#[cfg(not(feature = "devhost"))]
+pub fn dropped_count() -> usize {
+    os_deferred::DROPPED.load(Relaxed)
+}
+
 // ----- Macros ---------------------------------------------------------------
 
 #[macro_export]
@@ -131,77 +175,155 @@ macro_rules! log_trace {
     }
 }
 
-// ----- OS-mode serial sink (x86/x86_64) ------------------------------------
+// OS-mode serial output is implemented once, in `kernel::serial`, and
+// shared with `panic` so a fault report doesn't need its own UART code.
 
-#[cfg(not(feature = "devhost"))]
-struct SerialWriter;
+// ----- OS-mode deferred logging --------------------------------------------
 
 #[cfg(not(feature = "devhost"))]
-impl core::fmt::Write for SerialWriter {
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        serial_write_str(s);
-        Ok(())
+mod os_deferred {
+    use super::Level;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering::{Acquire, Relaxed, Release}};
+
+    pub(super) static DEFERRED: AtomicBool = AtomicBool::new(false);
+    pub(super) static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    pub(super) fn is_deferred() -> bool {
+        DEFERRED.load(Relaxed)
     }
-}
 
-#[cfg(not(feature = "devhost"))]
-#[inline(always)]
-fn serial_write_b(b: u8) {
-    // Wait for THR empty
-    while (unsafe { inb(COM1 + 5) } & 0x20) == 0 {}
-    unsafe { outb(COM1, b) };
-}
+    const RING_CAP: usize = 8192;
+    const MAX_RECORD: usize = 256;
 
-#[cfg(not(feature = "devhost"))]
-fn serial_write_str(s: &str) {
-    for &b in s.as_bytes() {
-        // Map '\n' to CRLF for common terminals.
-        if b == b'\n' { serial_write_b(b'\r'); }
-        serial_write_b(b);
+    // Byte ring, framed as `[len: u16 LE][len bytes]` per record so a
+    // reader never has to guess where one record ends and the next begins.
+    // `RING_LOCK` makes reservation + write atomic with respect to other
+    // producers; `HEAD`/`TAIL` track consumed/produced byte counts (mod
+    // `RING_CAP` on every index, so they can grow past the buffer size).
+    static mut RING: [u8; RING_CAP] = [0; RING_CAP];
+    static RING_LOCK: AtomicBool = AtomicBool::new(false);
+    static HEAD: AtomicUsize = AtomicUsize::new(0);
+    static TAIL: AtomicUsize = AtomicUsize::new(0);
+
+    /// Interrupt-safe critical section around `RING_LOCK`. `flush()` runs in
+    /// the main loop with interrupts enabled while `push()` is reachable
+    /// from exception/IRQ handlers on the same core; a plain spinlock would
+    /// let a handler's `push()` spin forever on a lock the preempted main
+    /// loop can never release. Disabling interrupts for the lock's short
+    /// hold time rules that out, and the prior IF state is restored (not
+    /// unconditionally re-enabled) so a guard taken from within a handler
+    /// that already has interrupts off doesn't turn them back on early.
+    struct RingGuard {
+        was_enabled: bool,
     }
-}
 
-#[cfg(not(feature = "devhost"))]
-const COM1: u16 = 0x3F8;
+    impl RingGuard {
+        fn acquire() -> Self {
+            let was_enabled = crate::kernel::gdt_idt::interrupts_enabled();
+            crate::kernel::gdt_idt::disable_interrupts();
+            while RING_LOCK.compare_exchange_weak(false, true, Acquire, Relaxed).is_err() {
+                core::hint::spin_loop();
+            }
+            RingGuard { was_enabled }
+        }
+    }
 
-#[cfg(not(feature = "devhost"))]
-unsafe fn serial_init() {
-    // 115200 8N1, FIFO on. IRQs off (polled TX).
-    outb(COM1 + 1, 0x00);       // disable interrupts
-    outb(COM1 + 3, 0x80);       // enable DLAB
-    outb(COM1 + 0, 0x01);       // divisor low (1) => 115200
-    outb(COM1 + 1, 0x00);       // divisor high
-    outb(COM1 + 3, 0x03);       // 8 bits, no parity, one stop
-    outb(COM1 + 2, 0xC7);       // enable FIFO, clear, 14-byte threshold
-    outb(COM1 + 4, 0x0B);       // DTR, RTS, OUT2
-}
+    impl Drop for RingGuard {
+        fn drop(&mut self) {
+            RING_LOCK.store(false, Release);
+            if self.was_enabled {
+                crate::kernel::gdt_idt::enable_interrupts();
+            }
+        }
+    }
 
-#[cfg(not(feature = "devhost"))]
-#[inline(always)]
-unsafe fn outb(port: u16, val: u8) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-    core::arch::asm!("out dx, al", in("dx") port, in("al") val, options(nomem, nostack, preserves_flags));
-}
+    /// Writes into a fixed-size stack buffer, silently truncating past
+    /// capacity (formatting a log line must never allocate or fail).
+    struct SliceWriter<'a> {
+        buf: &'a mut [u8],
+        len: usize,
+    }
 
-#[cfg(not(feature = "devhost"))]
-#[inline(always)]
-unsafe fn inb(port: u16) -> u8 {
-    let mut v: u8;
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
-    unsafe { core::arch::asm!("in al, dx", in("dx") port, out("al") v, options(nomem, nostack, preserves_flags)); }
-    v
-}
+    impl<'a> core::fmt::Write for SliceWriter<'a> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let bytes = s.as_bytes();
+            let remaining = self.buf.len() - self.len;
+            let n = bytes.len().min(remaining);
+            self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+            Ok(())
+        }
+    }
 
-// Minimal decimal writer (no alloc) for line numbers.
-#[cfg(not(feature = "devhost"))]
-fn decimal_u32(mut n: u32) {
-    let mut buf = [0u8; 10];
-    let mut i = 10;
-    if n == 0 { serial_write_b(b'0'); return; }
-    while n > 0 {
-        i -= 1;
-        buf[i] = b'0' + (n % 10) as u8;
-        n /= 10;
-    }
-    for &b in &buf[i..] { serial_write_b(b); }
+    pub(super) fn log_deferred(level: Level, file: &str, line: u32, args: core::fmt::Arguments) {
+        let tag = match level {
+            Level::Error => "E",
+            Level::Warn  => "W",
+            Level::Info  => "I",
+            Level::Debug => "D",
+            Level::Trace => "T",
+        };
+
+        let mut stack = [0u8; MAX_RECORD];
+        let mut w = SliceWriter { buf: &mut stack, len: 0 };
+        let _ = core::fmt::Write::write_fmt(&mut w, format_args!("[{tag}] {file}:{line} "));
+        let _ = core::fmt::Write::write_fmt(&mut w, args);
+        let _ = core::fmt::Write::write_str(&mut w, "\r\n");
+
+        push(&stack[..w.len]);
+    }
+
+    fn push(record: &[u8]) {
+        let framed_len = 2 + record.len();
+        let _guard = RingGuard::acquire();
+
+        let used = TAIL.load(Relaxed) - HEAD.load(Relaxed);
+        if RING_CAP - used < framed_len {
+            DROPPED.fetch_add(1, Relaxed);
+            return;
+        }
+
+        let tail = TAIL.load(Relaxed);
+        let len_bytes = (record.len() as u16).to_le_bytes();
+        unsafe {
+            RING[tail % RING_CAP] = len_bytes[0];
+            RING[(tail + 1) % RING_CAP] = len_bytes[1];
+            for (i, &b) in record.iter().enumerate() {
+                RING[(tail + 2 + i) % RING_CAP] = b;
+            }
+        }
+        TAIL.store(tail + framed_len, Release);
+    }
+
+    /// Drain every complete record currently in the ring to serial. Called
+    /// from the main loop with interrupts enabled; each iteration's
+    /// `RingGuard` briefly disables interrupts to copy one record out
+    /// (never for the slow serial write itself), so a same-core handler's
+    /// `push()` can't reenter mid-copy and deadlock on `RING_LOCK`.
+    pub(super) fn flush() {
+        loop {
+            let mut record = [0u8; MAX_RECORD];
+            let len;
+            {
+                let _guard = RingGuard::acquire();
+                let head = HEAD.load(Relaxed);
+                if head == TAIL.load(Relaxed) {
+                    return;
+                }
+                let raw_len = unsafe {
+                    u16::from_le_bytes([RING[head % RING_CAP], RING[(head + 1) % RING_CAP]]) as usize
+                };
+                let copy_len = raw_len.min(MAX_RECORD);
+                for i in 0..copy_len {
+                    record[i] = unsafe { RING[(head + 2 + i) % RING_CAP] };
+                }
+                HEAD.store(head + 2 + raw_len, Relaxed);
+                len = copy_len;
+            }
+            match core::str::from_utf8(&record[..len]) {
+                Ok(s) => crate::kernel::serial::write_str(s),
+                Err(_) => crate::kernel::serial::write_str("<deferred log record was not valid utf-8>\r\n"),
+            }
+        }
+    }
 }
@@ -1,17 +1,22 @@
 //! Asteria OS — Memory bring-up (dual mode).
 //!
 //! * **devhost (default)**: no-op stubs so CI/Codespaces build & run.
-//! * **OS mode (`--no-default-features`)**: exposes a tiny,
-//!   lock-free bump allocator you can point at a heap region
-//!   during early init. Dealloc is a no-op (first mile).
+//! * **OS mode (`--no-default-features`)**: exposes a free-list heap
+//!   allocator you can point at a heap region during early init. Freed
+//!   blocks are spliced back into an address-ordered free list and
+//!   coalesced with their neighbours, so long-running kernel code no
+//!   longer leaks every allocation. Each returned pointer is preceded by
+//!   a small header recording the true carved extent, so alignment
+//!   padding and any front/back slack too small for its own free block
+//!   are reclaimed on `dealloc` instead of leaking.
 //!
 //! Public API (stable):
 //!   memory::init()
 //!   memory::heap_stats() -> HeapStats
 //!   unsafe memory::set_heap_region(base, size)   [OS mode]
 //!
-//! Later you can replace the bump with a proper allocator
-//! and add paging/frames; keep the function names stable.
+//! Later you can add paging/frames on top of this; keep the function
+//! names stable.
 
 #![allow(dead_code)]
 #![allow(unused_variables)]
@@ -35,8 +40,11 @@ pub fn init() {
 pub struct HeapStats {
     pub base: usize,
     pub size: usize,
+    /// High-water mark: the farthest address ever handed out.
     pub next: usize,
     pub end: usize,
+    /// Bytes currently sitting in the free list (OS mode only; always 0 on devhost).
+    pub free_bytes: usize,
 }
 
 #[cfg(feature = "devhost")]
@@ -55,12 +63,18 @@ fn align_up(v: usize, a: usize) -> usize {
 mod os_heap {
     use super::HeapStats;
     use core::alloc::{GlobalAlloc, Layout};
-    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering::{Acquire, Relaxed, Release, SeqCst}};
 
-    // Heap bounds and bump pointer (shared across cores).
+    // Heap bounds and high-water mark (shared across cores).
     static HEAP_BASE: AtomicUsize = AtomicUsize::new(0);
     static HEAP_END:  AtomicUsize = AtomicUsize::new(0);
     static NEXT:      AtomicUsize = AtomicUsize::new(0);
+    static FREE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    // Free-list head, stored as a raw address (0 == empty). Guarded by
+    // `LIST_LOCK` since walking/splicing the list is not itself atomic.
+    static FREE_HEAD: AtomicUsize = AtomicUsize::new(0);
+    static LIST_LOCK: AtomicBool = AtomicBool::new(false);
 
     #[inline]
     fn align_up(v: usize, a: usize) -> usize {
@@ -68,13 +82,102 @@ mod os_heap {
         (v + mask) & !mask
     }
 
-    /// Configure the bump allocator with a raw region [base, base+size).
+    /// A free block's header, stored in the block's own first bytes.
+    #[repr(C)]
+    struct FreeNode {
+        size: usize,
+        next: *mut FreeNode,
+    }
+
+    const MIN_BLOCK: usize = core::mem::size_of::<FreeNode>();
+
+    /// Header stashed just before every pointer `FreeListAlloc` hands out,
+    /// recording the true `[true_addr, true_addr+true_size)` extent that was
+    /// carved out of the free list. Alignment padding and any front/back
+    /// slack too small to become its own `FreeNode` are folded into this
+    /// extent instead of being dropped, so `dealloc` always reclaims the
+    /// whole block it took, not just `layout.size()` at the aligned pointer.
+    #[repr(C)]
+    struct AllocHeader {
+        true_addr: usize,
+        true_size: usize,
+    }
+
+    const HEADER_SIZE: usize = core::mem::size_of::<AllocHeader>();
+
+    /// Spinlock guard over the free list (CAS retry loop; cheap and
+    /// shared-across-cores safe without needing an interrupt-off section).
+    struct ListGuard;
+
+    impl ListGuard {
+        fn acquire() -> Self {
+            while LIST_LOCK.compare_exchange_weak(false, true, Acquire, Relaxed).is_err() {
+                core::hint::spin_loop();
+            }
+            ListGuard
+        }
+    }
+
+    impl Drop for ListGuard {
+        fn drop(&mut self) {
+            LIST_LOCK.store(false, Release);
+        }
+    }
+
+    /// Splice a freed `[addr, addr+size)` block back into the free list in
+    /// address order, coalescing with an immediate predecessor/successor.
+    /// Caller must hold `ListGuard`.
+    unsafe fn free_insert(addr: usize, size: usize) {
+        let mut prev: *mut FreeNode = core::ptr::null_mut();
+        let mut cur = FREE_HEAD.load(SeqCst) as *mut FreeNode;
+        while !cur.is_null() && (cur as usize) < addr {
+            prev = cur;
+            cur = (*cur).next;
+        }
+
+        // Merge into the predecessor in place when the blocks are adjacent.
+        if !prev.is_null() && (prev as usize) + (*prev).size == addr {
+            (*prev).size += size;
+            if !cur.is_null() && (prev as usize) + (*prev).size == cur as usize {
+                (*prev).size += (*cur).size;
+                (*prev).next = (*cur).next;
+            }
+            FREE_BYTES.fetch_add(size, SeqCst);
+            return;
+        }
+
+        // Otherwise build a fresh node at `addr`, maybe absorbing the successor.
+        let node = addr as *mut FreeNode;
+        if !cur.is_null() && addr + size == cur as usize {
+            (*node).size = size + (*cur).size;
+            (*node).next = (*cur).next;
+        } else {
+            (*node).size = size;
+            (*node).next = cur;
+        }
+        if prev.is_null() {
+            FREE_HEAD.store(node as usize, SeqCst);
+        } else {
+            (*prev).next = node;
+        }
+        FREE_BYTES.fetch_add(size, SeqCst);
+    }
+
+    /// Configure the allocator with a raw region [base, base+size), seeding
+    /// the free list with the whole region as one block.
     /// # Safety
     /// Caller must ensure the region is valid, writable, and not used elsewhere.
     pub unsafe fn set_heap_region(base: usize, size: usize) {
         HEAP_BASE.store(base, SeqCst);
         HEAP_END.store(base.saturating_add(size), SeqCst);
         NEXT.store(base, SeqCst);
+        FREE_HEAD.store(0, SeqCst);
+        FREE_BYTES.store(0, SeqCst);
+
+        if size >= MIN_BLOCK {
+            let _guard = ListGuard::acquire();
+            free_insert(base, size);
+        }
     }
 
     /// Snapshot current heap numbers.
@@ -87,37 +190,91 @@ mod os_heap {
             size: end.saturating_sub(base),
             next,
             end,
+            free_bytes: FREE_BYTES.load(SeqCst),
         }
     }
 
-    /// Lock-free bump allocator. Dealloc is a no-op (first mile).
-    pub struct BumpAlloc;
+    /// Free-list allocator: first-fit search with front/back splitting on
+    /// alloc, address-ordered coalescing on dealloc. Every returned pointer
+    /// is preceded by an `AllocHeader` recording the true carved extent, so
+    /// `dealloc` reclaims the whole block — including alignment padding and
+    /// any front/back slack below `MIN_BLOCK` — not just `layout.size()`.
+    pub struct FreeListAlloc;
 
-    unsafe impl GlobalAlloc for BumpAlloc {
+    unsafe impl GlobalAlloc for FreeListAlloc {
         unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-            let mut cur = NEXT.load(SeqCst);
-            loop {
-                let aligned = align_up(cur, layout.align().max(1));
-                let new_next = aligned.saturating_add(layout.size());
-                let end = HEAP_END.load(SeqCst);
-                if new_next > end || aligned < cur {
-                    return core::ptr::null_mut();
-                }
-                match NEXT.compare_exchange(cur, new_next, SeqCst, SeqCst) {
-                    Ok(_) => return aligned as *mut u8,
-                    Err(actual) => cur = actual,
+            let align = layout.align().max(core::mem::align_of::<AllocHeader>()).max(1);
+            let size = layout.size();
+            let _guard = ListGuard::acquire();
+
+            let mut prev: *mut FreeNode = core::ptr::null_mut();
+            let mut cur = FREE_HEAD.load(SeqCst) as *mut FreeNode;
+
+            while !cur.is_null() {
+                let node_addr = cur as usize;
+                let node_size = (*cur).size;
+                let node_end = node_addr + node_size;
+                // Room for the header always precedes the aligned user pointer.
+                let aligned = align_up(node_addr + HEADER_SIZE, align);
+                let user_end = aligned + size;
+
+                if user_end <= node_end {
+                    let next = (*cur).next;
+                    if prev.is_null() {
+                        FREE_HEAD.store(next as usize, SeqCst);
+                    } else {
+                        (*prev).next = next;
+                    }
+                    FREE_BYTES.fetch_sub(node_size, SeqCst);
+
+                    let header_addr = aligned - HEADER_SIZE;
+                    let front_gap = header_addr - node_addr;
+                    let back_gap = node_end - user_end;
+
+                    // Gaps big enough to stand alone go back on the free
+                    // list; anything smaller is folded into this
+                    // allocation's extent so it's recovered on dealloc
+                    // instead of leaking.
+                    let true_addr = if front_gap >= MIN_BLOCK {
+                        free_insert(node_addr, front_gap);
+                        header_addr
+                    } else {
+                        node_addr
+                    };
+                    let true_end = if back_gap >= MIN_BLOCK {
+                        free_insert(user_end, back_gap);
+                        user_end
+                    } else {
+                        node_end
+                    };
+
+                    let header = header_addr as *mut AllocHeader;
+                    (*header).true_addr = true_addr;
+                    (*header).true_size = true_end - true_addr;
+
+                    NEXT.fetch_max(true_end, SeqCst);
+                    return aligned as *mut u8;
                 }
+
+                prev = cur;
+                cur = (*cur).next;
             }
+
+            core::ptr::null_mut()
         }
 
-        unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
-            // No free in first-mile bump. Fragmentation is fine for now.
+        unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+            let header = (ptr as usize - HEADER_SIZE) as *mut AllocHeader;
+            let true_addr = (*header).true_addr;
+            let true_size = (*header).true_size;
+            let _guard = ListGuard::acquire();
+            free_insert(true_addr, true_size);
         }
     }
 
     // Install as the global allocator only in OS mode.
     #[global_allocator]
-    static GLOBAL: BumpAlloc = BumpAlloc;
+    static GLOBAL: FreeListAlloc = FreeListAlloc;
 
     /// OOM path required for `alloc` in no_std.
     #[alloc_error_handler]
@@ -2,8 +2,10 @@
 //!
 //! - **devhost (default)**: install a pretty stderr hook so panics
 //!   show file:line:col and a message in Codespaces/CI.
-//! - **OS mode (no_std)**: halt the CPU safely; later you can add a
-//!   framebuffer/serial panic screen.
+//! - **OS mode (no_std)**: write a `"KERNEL PANIC"` report (location +
+//!   message) to COM1 via `kernel::serial`, then, if a framebuffer
+//!   `Painter` was registered with `set_framebuffer`, clear it to dark
+//!   red and draw a panel with the fault text, before halting.
 
 #[cfg(feature = "devhost")]
 pub fn install_panic_hook() {
@@ -35,25 +37,63 @@ pub fn install_panic_hook() {
     }));
 }
 
-// no_std OS-mode panic: disable interrupts (implicitly by HLT loop) and halt.
+// no_std OS-mode panic: serial diagnostic, optional framebuffer panel, then halt.
 #[cfg(not(feature = "devhost"))]
 use core::panic::PanicInfo;
 
+/// Raw pointer to the framebuffer painter the panic handler should draw
+/// into, if any has been registered. The handler can't take arguments, so
+/// this is how it learns where the screen is.
+#[cfg(not(feature = "devhost"))]
+static mut FRAMEBUFFER: *mut ui::Painter = core::ptr::null_mut();
+
+/// Register the framebuffer the panic handler should draw a panel on.
+/// # Safety
+/// `painter` must stay valid and exclusively owned by the panic handler
+/// from this point on — the handler may dereference it from any context.
+#[cfg(not(feature = "devhost"))]
+pub unsafe fn set_framebuffer(painter: *mut ui::Painter) {
+    FRAMEBUFFER = painter;
+}
+
 #[cfg(not(feature = "devhost"))]
 #[panic_handler]
-fn panic(_info: &PanicInfo) -> ! {
-    // OPTIONAL: later, write a minimal message to serial (0x3F8) or draw a panic
-    // rectangle on the framebuffer. Keep it simple for first bring-up.
-
-    loop {
-        // Halt until next interrupt to avoid burning CPU; keeps system stable at panic site.
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        unsafe {
-            core::arch::asm!("hlt");
-        }
+fn panic(info: &PanicInfo) -> ! {
+    use crate::kernel::serial;
+
+    serial::write_str("KERNEL PANIC\n");
+    if let Some(loc) = info.location() {
+        serial::write_str(loc.file());
+        serial::write_byte(b':');
+        serial::decimal_u32(loc.line());
+        serial::write_byte(b':');
+        serial::decimal_u32(loc.column());
+        serial::write_byte(b'\n');
+    }
+    let mut w = serial::Writer;
+    let _ = core::fmt::Write::write_fmt(&mut w, format_args!("{}\n", info.message()));
 
-        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
-        core::hint::spin_loop();
+    let painter = unsafe { FRAMEBUFFER };
+    if !painter.is_null() {
+        draw_panic_panel(unsafe { &mut *painter }, info);
     }
+
+    crate::kernel::gdt_idt::cli_hlt();
+}
+
+#[cfg(not(feature = "devhost"))]
+fn draw_panic_panel(p: &mut ui::Painter, info: &PanicInfo) {
+    p.clear_rgb(96, 0, 0);
+
+    let (w, h) = (p.width(), p.height());
+    let content = ui::panel(p, ui::Rect::new(16, 16, (w - 32).max(0), (h - 32).max(0)));
+    let fg = ui::Color::rgb(255, 224, 224);
+
+    ui::draw_text(p, content.x, content.y, "KERNEL PANIC", fg, None);
+    if let Some(loc) = info.location() {
+        ui::draw_text(p, content.x, content.y + ui::FONT_H, loc.file(), fg, None);
+    }
+
+    p.present();
 }
 
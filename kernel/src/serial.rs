@@ -0,0 +1,72 @@
+//! Shared COM1 serial primitives for Asteria OS (OS mode only).
+//!
+//! `logger` and `panic` both need to write raw bytes to COM1; this module
+//! holds the one polled-UART implementation so neither duplicates the port
+//! plumbing or the decimal formatting helper.
+
+#![cfg(not(feature = "devhost"))]
+#![allow(dead_code)]
+
+pub(crate) const COM1: u16 = 0x3F8;
+
+/// Configure COM1 for 115200 8N1, FIFO on, polled TX (IRQs off).
+pub(crate) unsafe fn init() {
+    outb(COM1 + 1, 0x00); // disable interrupts
+    outb(COM1 + 3, 0x80); // enable DLAB
+    outb(COM1 + 0, 0x01); // divisor low (1) => 115200
+    outb(COM1 + 1, 0x00); // divisor high
+    outb(COM1 + 3, 0x03); // 8 bits, no parity, one stop
+    outb(COM1 + 2, 0xC7); // enable FIFO, clear, 14-byte threshold
+    outb(COM1 + 4, 0x0B); // DTR, RTS, OUT2
+}
+
+/// Write one byte, blocking until the transmit holding register is empty.
+pub(crate) fn write_byte(b: u8) {
+    while (unsafe { inb(COM1 + 5) } & 0x20) == 0 {}
+    unsafe { outb(COM1, b) };
+}
+
+/// Write a string, mapping `'\n'` to CRLF for common terminals.
+pub(crate) fn write_str(s: &str) {
+    for &b in s.as_bytes() {
+        if b == b'\n' { write_byte(b'\r'); }
+        write_byte(b);
+    }
+}
+
+/// Minimal decimal writer (no alloc), used for line numbers and similar.
+pub(crate) fn decimal_u32(mut n: u32) {
+    let mut buf = [0u8; 10];
+    let mut i = 10;
+    if n == 0 { write_byte(b'0'); return; }
+    while n > 0 {
+        i -= 1;
+        buf[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+    }
+    for &b in &buf[i..] { write_byte(b); }
+}
+
+/// `core::fmt::Write` sink over COM1, for `write!`/`write_fmt` call sites.
+pub(crate) struct Writer;
+
+impl core::fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        write_str(s);
+        Ok(())
+    }
+}
+
+#[inline(always)]
+unsafe fn outb(port: u16, val: u8) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    core::arch::asm!("out dx, al", in("dx") port, in("al") val, options(nomem, nostack, preserves_flags));
+}
+
+#[inline(always)]
+unsafe fn inb(port: u16) -> u8 {
+    let mut v: u8 = 0;
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    core::arch::asm!("in al, dx", in("dx") port, out("al") v, options(nomem, nostack, preserves_flags));
+    v
+}
@@ -12,11 +12,171 @@ fn main() {
 #[cfg(not(feature = "devhost"))]
 #![no_main]
 
+#[cfg(not(feature = "devhost"))]
+mod kernel {
+    #[path = "kernel/src/memory.rs"] pub mod memory;
+    #[path = "kernel/src/logger.rs"] pub mod logger;
+    #[path = "kernel/src/serial.rs"] pub mod serial;
+    #[path = "kernel/src/panic.rs"] pub mod panic;
+    #[path = "kernel/src/gdt_idt.rs"] pub mod gdt_idt;
+    #[path = "kernel/src/interrupts.rs"] pub mod interrupts;
+    #[path = "kernel/src/input.rs"] pub mod input;
+}
+
 #[cfg(not(feature = "devhost"))]
 mod os_mode {
-    use core::panic::PanicInfo;
-    #[panic_handler]
-    fn panic(_info: &PanicInfo) -> ! { loop {} }
+    //! Limine boot entry: the first boot where pixels actually reach the
+    //! screen. Requests a framebuffer and the memory map, hands the largest
+    //! usable region to `memory::set_heap_region`, and builds a real
+    //! `Painter` over the Limine framebuffer instead of the stub surface.
+
+    const LIMINE_COMMON_MAGIC: [u64; 2] = [0xc7b1dd30df4c8b88, 0x0a82e883a194f07b];
+
+    #[repr(C)]
+    struct BaseRevision {
+        id: [u64; 2],
+        revision: u64,
+    }
+    #[used]
+    #[link_section = ".requests"]
+    static BASE_REVISION: BaseRevision =
+        BaseRevision { id: [0xf9562b2d5c95a6c8, 0x6a7b384944536bdc], revision: 2 };
+
+    #[repr(C)]
+    struct FramebufferRequest {
+        id: [u64; 4],
+        revision: u64,
+        response: *mut FramebufferResponse,
+    }
+    unsafe impl Sync for FramebufferRequest {}
+
+    #[repr(C)]
+    struct FramebufferResponse {
+        revision: u64,
+        framebuffer_count: u64,
+        framebuffers: *mut *mut Framebuffer,
+    }
+
+    #[repr(C)]
+    struct Framebuffer {
+        address: *mut u8,
+        width: u64,
+        height: u64,
+        pitch: u64,
+        bpp: u16,
+        memory_model: u8,
+        red_mask_size: u8,
+        red_mask_shift: u8,
+        green_mask_size: u8,
+        green_mask_shift: u8,
+        blue_mask_size: u8,
+        blue_mask_shift: u8,
+        unused: [u8; 7],
+        edid_size: u64,
+        edid: *mut u8,
+        mode_count: u64,
+        modes: *mut *mut core::ffi::c_void,
+    }
+
+    #[used]
+    #[link_section = ".requests"]
+    static FRAMEBUFFER_REQUEST: FramebufferRequest = FramebufferRequest {
+        id: [LIMINE_COMMON_MAGIC[0], LIMINE_COMMON_MAGIC[1], 0x9d5827dcd881dd75, 0xa3148604f6fab11b],
+        revision: 0,
+        response: core::ptr::null_mut(),
+    };
+
+    #[repr(C)]
+    struct MemmapRequest {
+        id: [u64; 4],
+        revision: u64,
+        response: *mut MemmapResponse,
+    }
+    unsafe impl Sync for MemmapRequest {}
+
+    #[repr(C)]
+    struct MemmapResponse {
+        revision: u64,
+        entry_count: u64,
+        entries: *mut *mut MemmapEntry,
+    }
+
+    #[repr(C)]
+    struct MemmapEntry {
+        base: u64,
+        length: u64,
+        kind: u64,
+    }
+    const MEMMAP_KIND_USABLE: u64 = 0;
+
+    #[used]
+    #[link_section = ".requests"]
+    static MEMMAP_REQUEST: MemmapRequest = MemmapRequest {
+        id: [LIMINE_COMMON_MAGIC[0], LIMINE_COMMON_MAGIC[1], 0x67cf3d9d378a806f, 0xe304acdfc50c3c62],
+        revision: 0,
+        response: core::ptr::null_mut(),
+    };
+
+    /// Pick the largest `USABLE` region from the Limine memory map and hand
+    /// it to the heap allocator. No-op (leaves the heap unset) if Limine
+    /// didn't answer the memmap request.
+    unsafe fn init_heap_from_memmap() {
+        let resp = MEMMAP_REQUEST.response;
+        if resp.is_null() {
+            return;
+        }
+        let resp = &*resp;
+
+        let mut best_base = 0u64;
+        let mut best_len = 0u64;
+        for i in 0..resp.entry_count {
+            let entry = &**resp.entries.add(i as usize);
+            if entry.kind == MEMMAP_KIND_USABLE && entry.length > best_len {
+                best_base = entry.base;
+                best_len = entry.length;
+            }
+        }
+        if best_len > 0 {
+            crate::kernel::memory::set_heap_region(best_base as usize, best_len as usize);
+        }
+    }
+
+    /// Build a `Painter` over the Limine framebuffer, or the zero-alloc stub
+    /// if Limine didn't answer the framebuffer request.
+    unsafe fn make_painter() -> ui::Painter {
+        let resp = FRAMEBUFFER_REQUEST.response;
+        if resp.is_null() || (*resp).framebuffer_count == 0 {
+            return ui::Painter::new_os_stub(640, 360);
+        }
+        let fb = &**(*resp).framebuffers;
+        ui::Painter::new_framebuffer(fb.address, fb.pitch as usize, fb.width as i32, fb.height as i32)
+    }
+
+    static mut PAINTER: Option<ui::Painter> = None;
+
+    #[no_mangle]
+    extern "C" fn _start() -> ! {
+        unsafe {
+            init_heap_from_memmap();
+
+            PAINTER = Some(make_painter());
+            let painter_ptr = PAINTER.as_mut().unwrap() as *mut ui::Painter;
+            crate::kernel::panic::set_framebuffer(painter_ptr);
+        }
+
+        crate::kernel::logger::init();
+        crate::kernel::gdt_idt::init();
+        crate::kernel::interrupts::init();
+        crate::kernel::input::init();
+
+        // From here on, logging can happen inside IRQ handlers (keyboard,
+        // timer, ...); defer it so they never block on slow serial I/O.
+        crate::kernel::logger::set_deferred(true);
+        crate::kernel::interrupts::enable();
 
-    // Bootloader entry will be added here later.
+        loop {
+            crate::kernel::logger::flush();
+            crate::kernel::gdt_idt::halt();
+        }
+    }
 }